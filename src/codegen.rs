@@ -0,0 +1,308 @@
+use crate::model;
+
+/// A code-generation backend turning the parsed [`model::Aidl`] into typed
+/// stubs for a target language.
+///
+/// Each backend knows how to render a single [`model::Item`] (interface,
+/// parcelable or enum) and how to map an AIDL type string to the target
+/// language's type system. `oneway` methods are rendered as fire-and-forget
+/// signatures (no return value).
+pub trait Backend {
+    /// Render a single item as a stand-alone declaration.
+    fn item(&self, item: &model::Item) -> String;
+
+    /// Map an AIDL type (as produced by [`model::ast_type_to_string`]) to the
+    /// target language type.
+    fn map_type(&self, aidl_type: &str) -> String;
+}
+
+/// Resolve a backend from the `--generate <lang>` argument.
+pub fn backend_for(lang: &str) -> Option<Box<dyn Backend>> {
+    match lang.to_lowercase().as_str() {
+        "ts" | "typescript" => Some(Box::new(TypeScriptBackend)),
+        "rs" | "rust" => Some(Box::new(RustBackend)),
+        _ => None,
+    }
+}
+
+// Split an AIDL container type `Name<A, B>` into its name and the list of
+// generic argument strings. Returns `None` for non-generic types.
+fn split_generic(aidl_type: &str) -> Option<(&str, Vec<&str>)> {
+    let open = aidl_type.find('<')?;
+    if !aidl_type.ends_with('>') {
+        return None;
+    }
+    let name = aidl_type[..open].trim();
+    let inner = &aidl_type[open + 1..aidl_type.len() - 1];
+
+    // Split the top-level generic arguments on commas, respecting nesting.
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(inner[start..].trim());
+
+    Some((name, args))
+}
+
+// Reduce a resolved AIDL type to a bare, syntactically valid type name.
+//
+// `model::ast_type_to_string` returns the fully-qualified name for a resolved
+// type (e.g. `com.example.Point`), whose dots are not valid in either target's
+// type position. Keep only the final segment so stubs referring to a declared
+// type compile.
+fn last_segment(aidl_type: &str) -> String {
+    aidl_type.rsplit('.').next().unwrap_or(aidl_type).to_string()
+}
+
+/// TypeScript backend: interfaces become classes with one method each,
+/// parcelables become interfaces and enums become TypeScript enums.
+pub struct TypeScriptBackend;
+
+impl Backend for TypeScriptBackend {
+    fn map_type(&self, aidl_type: &str) -> String {
+        if let Some((name, args)) = split_generic(aidl_type) {
+            return match name {
+                "List" => format!("Array<{}>", self.map_type(args[0])),
+                "Map" if args.len() == 2 => {
+                    format!("Map<{}, {}>", self.map_type(args[0]), self.map_type(args[1]))
+                }
+                _ => aidl_type.to_string(),
+            };
+        }
+
+        match aidl_type {
+            "void" => "void".to_string(),
+            "boolean" => "boolean".to_string(),
+            "byte" | "char" | "int" | "long" | "float" | "double" => "number".to_string(),
+            "String" | "CharSequence" => "string".to_string(),
+            other => last_segment(other),
+        }
+    }
+
+    fn item(&self, item: &model::Item) -> String {
+        let mut out = String::new();
+        if let Some(doc) = &item.doc {
+            out.push_str(&format!("/** {} */\n", doc));
+        }
+
+        match item.item_type {
+            model::ItemType::Interface => {
+                out.push_str(&format!("export class {} {{\n", item.name));
+                for el in item.elements.values() {
+                    match el {
+                        model::Element::Method {
+                            oneway,
+                            name,
+                            return_type,
+                            args,
+                            ..
+                        } => {
+                            // TypeScript has no `out`/`inout` parameter
+                            // concept, so arg direction is intentionally elided
+                            // here; the Rust backend reflects it via `&mut`.
+                            let params = args
+                                .iter()
+                                .enumerate()
+                                .map(|(i, a)| {
+                                    let n = a
+                                        .name
+                                        .clone()
+                                        .unwrap_or_else(|| format!("arg{}", i));
+                                    format!("{}: {}", n, self.map_type(&a.arg_type))
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let ret = if *oneway {
+                                "void".to_string()
+                            } else {
+                                self.map_type(return_type)
+                            };
+                            out.push_str(&format!(
+                                "  {}({}): Promise<{}> {{ throw new Error(\"not implemented\"); }}\n",
+                                name, params, ret
+                            ));
+                        }
+                        model::Element::Const {
+                            name, const_type, value, ..
+                        } => {
+                            out.push_str(&format!(
+                                "  static readonly {}: {} = {};\n",
+                                name,
+                                self.map_type(const_type),
+                                value
+                            ));
+                        }
+                        _ => {}
+                    }
+                }
+                out.push_str("}\n");
+            }
+            model::ItemType::Parcelable => {
+                out.push_str(&format!("export interface {} {{\n", item.name));
+                for el in item.elements.values() {
+                    if let model::Element::Field {
+                        name, field_type, ..
+                    } = el
+                    {
+                        out.push_str(&format!("  {}: {};\n", name, self.map_type(field_type)));
+                    }
+                }
+                out.push_str("}\n");
+            }
+            model::ItemType::Enum => {
+                out.push_str(&format!("export enum {} {{\n", item.name));
+                for el in item.elements.values() {
+                    if let model::Element::EnumElement { name, value, .. } = el {
+                        match value {
+                            Some(v) => out.push_str(&format!("  {} = {},\n", name, v)),
+                            None => out.push_str(&format!("  {},\n", name)),
+                        }
+                    }
+                }
+                out.push_str("}\n");
+            }
+        }
+
+        out
+    }
+}
+
+/// Rust backend: interfaces become traits, parcelables become structs and
+/// enums become Rust enums.
+pub struct RustBackend;
+
+impl Backend for RustBackend {
+    fn map_type(&self, aidl_type: &str) -> String {
+        if let Some((name, args)) = split_generic(aidl_type) {
+            return match name {
+                "List" => format!("Vec<{}>", self.map_type(args[0])),
+                "Map" if args.len() == 2 => {
+                    format!(
+                        "std::collections::HashMap<{}, {}>",
+                        self.map_type(args[0]),
+                        self.map_type(args[1])
+                    )
+                }
+                _ => aidl_type.to_string(),
+            };
+        }
+
+        match aidl_type {
+            "void" => "()".to_string(),
+            "boolean" => "bool".to_string(),
+            "byte" => "i8".to_string(),
+            "char" => "u16".to_string(),
+            "int" => "i32".to_string(),
+            "long" => "i64".to_string(),
+            "float" => "f32".to_string(),
+            "double" => "f64".to_string(),
+            "String" | "CharSequence" => "String".to_string(),
+            other => last_segment(other),
+        }
+    }
+
+    fn item(&self, item: &model::Item) -> String {
+        let mut out = String::new();
+        if let Some(doc) = &item.doc {
+            out.push_str(&format!("/// {}\n", doc));
+        }
+
+        match item.item_type {
+            model::ItemType::Interface => {
+                out.push_str(&format!("pub trait {} {{\n", item.name));
+                for el in item.elements.values() {
+                    match el {
+                        model::Element::Method {
+                            oneway,
+                            name,
+                            return_type,
+                            args,
+                            ..
+                        } => {
+                            let params = args
+                                .iter()
+                                .enumerate()
+                                .map(|(i, a)| {
+                                    let n = a
+                                        .name
+                                        .clone()
+                                        .unwrap_or_else(|| format!("arg{}", i));
+                                    // `out`/`inout` args are mutated in place by
+                                    // the callee, so borrow them mutably.
+                                    let ty = match a.direction {
+                                        model::Direction::Out | model::Direction::InOut => {
+                                            format!("&mut {}", self.map_type(&a.arg_type))
+                                        }
+                                        _ => self.map_type(&a.arg_type),
+                                    };
+                                    format!("{}: {}", n, ty)
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            if *oneway {
+                                out.push_str(&format!("    fn {}(&self, {});\n", name, params));
+                            } else {
+                                out.push_str(&format!(
+                                    "    fn {}(&self, {}) -> {};\n",
+                                    name,
+                                    params,
+                                    self.map_type(return_type)
+                                ));
+                            }
+                        }
+                        model::Element::Const {
+                            name, const_type, value, ..
+                        } => {
+                            out.push_str(&format!(
+                                "    const {}: {} = {};\n",
+                                name,
+                                self.map_type(const_type),
+                                value
+                            ));
+                        }
+                        _ => {}
+                    }
+                }
+                out.push_str("}\n");
+            }
+            model::ItemType::Parcelable => {
+                out.push_str(&format!("pub struct {} {{\n", item.name));
+                for el in item.elements.values() {
+                    if let model::Element::Field {
+                        name, field_type, ..
+                    } = el
+                    {
+                        out.push_str(&format!(
+                            "    pub {}: {},\n",
+                            name,
+                            self.map_type(field_type)
+                        ));
+                    }
+                }
+                out.push_str("}\n");
+            }
+            model::ItemType::Enum => {
+                out.push_str(&format!("pub enum {} {{\n", item.name));
+                for el in item.elements.values() {
+                    if let model::Element::EnumElement { name, .. } = el {
+                        out.push_str(&format!("    {},\n", name));
+                    }
+                }
+                out.push_str("}\n");
+            }
+        }
+
+        out
+    }
+}
@@ -15,7 +15,11 @@ use codespan_reporting::{
 use structopt::StructOpt;
 use walkdir::WalkDir;
 
+mod codegen;
 mod model;
+mod overlay;
+mod query;
+mod references;
 
 #[derive(Debug, StructOpt)]
 struct Opt {
@@ -39,19 +43,50 @@ struct Opt {
     #[structopt(short = "y", long)]
     to_yaml: bool,
 
+    /// Print the JSON Schema describing the serialized model and exit
+    #[structopt(long)]
+    emit_schema: bool,
+
+    /// Select items/elements with a selector (e.g. `itemType=interface,element.oneway=true`)
+    #[structopt(long)]
+    query: Option<String>,
+
+    /// Print everything referencing the given fully-qualified type name
+    #[structopt(long = "reverse-deps")]
+    reverse_deps: Option<String>,
+
+    /// Include the type cross-reference graph in JSON/YAML output
+    #[structopt(long)]
+    emit_references: bool,
+
+    /// Apply an overlay file (YAML) merging metadata onto the parsed model
+    #[structopt(long, parse(from_os_str))]
+    overlay: Option<PathBuf>,
+
+    /// Generate typed client/server stubs in the given language (ts, rust)
+    #[structopt(short = "g", long)]
+    generate: Option<String>,
+
     /// Output file
     #[structopt(short = "o", long, parse(from_os_str))]
     output_path: Option<PathBuf>,
 
     /// The directory where the AIDL files are located
-    #[structopt(parse(from_os_str))]
-    dir: PathBuf,
+    #[structopt(parse(from_os_str), required_unless = "emit-schema")]
+    dir: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
     // Command line options
     let opt = Opt::from_args();
 
+    // Emit the JSON Schema and exit before touching the filesystem
+    if opt.emit_schema {
+        let schema = schemars::schema_for!(model::Aidl);
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+
     // Parse files
     let files = SimpleFiles::new();
     parse(files, &opt)?;
@@ -60,10 +95,11 @@ fn main() -> Result<()> {
 }
 
 fn parse(mut files: SimpleFiles<String, String>, opt: &Opt) -> Result<()> {
-    let root_path = opt.dir.as_path();
+    let dir = opt.dir.as_ref().expect("dir is required unless --emit-schema");
+    let root_path = dir.as_path();
 
     // Walk through the directory and find all AIDL files
-    let dir_entries = WalkDir::new(&opt.dir)
+    let dir_entries = WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
@@ -116,8 +152,19 @@ fn parse(mut files: SimpleFiles<String, String>, opt: &Opt) -> Result<()> {
         }
     }
 
-    // Convert to JSON
-    convert(&files, &parse_results, opt)?;
+    // Build the model once, then dispatch to a single mutually-exclusive mode.
+    let mut aidl = build_aidl(&files, &parse_results)?;
+    apply_overlay(&mut aidl, opt)?;
+
+    if opt.generate.is_some() {
+        generate(&aidl, opt)?;
+    } else if opt.query.is_some() {
+        run_query(&aidl, opt)?;
+    } else if opt.reverse_deps.is_some() {
+        reverse_deps(&aidl, opt)?;
+    } else if opt.to_json || opt.to_yaml {
+        convert(&aidl, opt)?;
+    }
 
     Ok(())
 }
@@ -216,11 +263,7 @@ fn to_codespan_diagnostic(
         })
 }
 
-fn convert(
-    files: &SimpleFiles<String, String>,
-    parse_results: &HashMap<usize, ParseFileResult<usize>>,
-    opt: &Opt,
-) -> Result<()> {
+fn convert(aidl: &model::Aidl, opt: &Opt) -> Result<()> {
     enum OutputKind {
         Json,
         Yaml,
@@ -233,8 +276,56 @@ fn convert(
         return Ok(());
     };
 
-    let items = parse_results
-        .iter()
+    // Optionally attach the type cross-reference graph (opt-in section).
+    let mut aidl = aidl.clone();
+    if opt.emit_references {
+        let refs = references::build(&aidl);
+        aidl.references = refs.references;
+        aidl.dangling_references = refs.dangling;
+    }
+
+    let output = match output_kind {
+        OutputKind::Json => {
+            if opt.pretty {
+                serde_json::to_string_pretty(&aidl)?
+            } else {
+                serde_json::to_string(&aidl)?
+            }
+        }
+        OutputKind::Yaml => serde_yaml::to_string(&aidl)?,
+    };
+
+    if let Some(path) = opt.output_path.as_ref() {
+        // Write JSON to output file
+        let path = std::fs::canonicalize(path)?;
+        let mut file = std::fs::File::create(&path)?;
+        writeln!(file, "{}\n", output)?;
+    } else {
+        // Write JSON to stdout
+        println!("{}\n", output)
+    };
+
+    Ok(())
+}
+
+// Build the in-memory model from the parse results.
+fn build_aidl(
+    files: &SimpleFiles<String, String>,
+    parse_results: &HashMap<usize, ParseFileResult<usize>>,
+) -> Result<model::Aidl> {
+    // Iterate in file-path order so cross-file item order is reproducible
+    // regardless of the filesystem-dependent WalkDir traversal.
+    let mut entries: Vec<_> = parse_results.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| {
+        files
+            .get(**a)
+            .unwrap()
+            .name()
+            .cmp(files.get(**b).unwrap().name())
+    });
+
+    let items = entries
+        .into_iter()
         .filter_map(|(id, res)| {
             res.ast.as_ref().map(|ast| {
                 let path = PathBuf::from(files.get(*id).unwrap().name())
@@ -253,31 +344,90 @@ fn convert(
         })
         .collect();
 
-    let aidl = model::Aidl {
+    Ok(model::Aidl {
+        format_version: model::FORMAT_VERSION,
         root: std::env::current_dir()?.to_string_lossy().to_string(),
         items,
-    };
+        references: Default::default(),
+        dangling_references: Default::default(),
+    })
+}
 
-    let output = match output_kind {
-        OutputKind::Json => {
-            if opt.pretty {
-                serde_json::to_string_pretty(&aidl)?
-            } else {
-                serde_json::to_string(&aidl)?
-            }
-        }
-        OutputKind::Yaml => serde_yaml::to_string(&aidl)?,
-    };
+// Merge an overlay file onto the model, if one was requested.
+fn apply_overlay(aidl: &mut model::Aidl, opt: &Opt) -> Result<()> {
+    if let Some(path) = opt.overlay.as_ref() {
+        let entries = overlay::load(path)?;
+        overlay::apply(aidl, &entries);
+    }
+    Ok(())
+}
+
+// Generate typed stubs from the parsed model
+fn generate(aidl: &model::Aidl, opt: &Opt) -> Result<()> {
+    let lang = opt.generate.as_ref().expect("generate mode requires --generate");
+
+    let backend = codegen::backend_for(lang)
+        .ok_or_else(|| anyhow::anyhow!("unsupported --generate language: {}", lang))?;
+
+    // Emit all items as a single bundle, one declaration after the other.
+    let mut bundle = String::new();
+    for item in aidl.items.values() {
+        bundle.push_str(&backend.item(item));
+        bundle.push('\n');
+    }
 
     if let Some(path) = opt.output_path.as_ref() {
-        // Write JSON to output file
-        let path = std::fs::canonicalize(path)?;
-        let mut file = std::fs::File::create(&path)?;
-        writeln!(file, "{}\n", output)?;
+        let mut file = std::fs::File::create(path)?;
+        write!(file, "{}", bundle)?;
     } else {
-        // Write JSON to stdout
-        println!("{}\n", output)
-    };
+        print!("{}", bundle);
+    }
+
+    Ok(())
+}
+
+// Run a query and print the matches: a reduced JSON/YAML document when an
+// output format is requested, otherwise a flat list of paths.
+fn run_query(aidl: &model::Aidl, opt: &Opt) -> Result<()> {
+    let selector = opt.query.as_ref().expect("query mode requires --query");
+    let q = query::Query::parse(selector).map_err(|e| anyhow::anyhow!(e))?;
+
+    if opt.to_json {
+        let reduced = q.filter(aidl);
+        let output = if opt.pretty {
+            serde_json::to_string_pretty(&reduced)?
+        } else {
+            serde_json::to_string(&reduced)?
+        };
+        println!("{}", output);
+    } else if opt.to_yaml {
+        println!("{}", serde_yaml::to_string(&q.filter(aidl))?);
+    } else {
+        for line in q.flat(aidl) {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+// Print every element path depending on the given fully-qualified type name.
+fn reverse_deps(aidl: &model::Aidl, opt: &Opt) -> Result<()> {
+    let fqn = opt
+        .reverse_deps
+        .as_ref()
+        .expect("reverse-deps mode requires --reverse-deps");
+
+    let refs = references::build(aidl);
+
+    match refs.references.get(fqn) {
+        Some(sources) => {
+            for source in sources {
+                println!("{}", source);
+            }
+        }
+        None => eprintln!("no references to {}", fqn),
+    }
 
     Ok(())
 }
@@ -293,6 +443,7 @@ fn convert_interface(path: String, i: &ast::Interface) -> model::Item {
                     name: c.name.clone(),
                     const_type: model::ast_type_to_string(&c.const_type),
                     value: c.value.clone(),
+                    line: c.symbol_range.start.line_col.0,
                 },
             ),
             ast::InterfaceElement::Method(m) => (
@@ -301,6 +452,7 @@ fn convert_interface(path: String, i: &ast::Interface) -> model::Item {
                     oneway: m.oneway,
                     name: m.name.clone(),
                     return_type: model::ast_type_to_string(&m.return_type),
+                    line: m.symbol_range.start.line_col.0,
                     args: m
                         .args
                         .iter()
@@ -324,6 +476,8 @@ fn convert_interface(path: String, i: &ast::Interface) -> model::Item {
         item_type: model::ItemType::Interface,
         elements,
         doc: i.doc.as_ref().cloned(),
+        annotations: Default::default(),
+        line: i.symbol_range.start.line_col.0,
     }
 }
 
@@ -336,6 +490,7 @@ fn convert_parcelable(path: String, p: &ast::Parcelable) -> model::Item {
                 name: f.name.clone(),
                 field_type: model::ast_type_to_string(&f.field_type),
                 doc: f.doc.as_ref().cloned(),
+                line: f.symbol_range.start.line_col.0,
             };
             (f.name.clone(), element)
         })
@@ -347,6 +502,8 @@ fn convert_parcelable(path: String, p: &ast::Parcelable) -> model::Item {
         item_type: model::ItemType::Parcelable,
         elements,
         doc: p.doc.as_ref().cloned(),
+        annotations: Default::default(),
+        line: p.symbol_range.start.line_col.0,
     }
 }
 
@@ -359,6 +516,7 @@ fn convert_enum(path: String, e: &ast::Enum) -> model::Item {
                 name: el.name.clone(),
                 value: el.value.clone(),
                 doc: el.doc.as_ref().cloned(),
+                line: el.symbol_range.start.line_col.0,
             };
             (el.name.clone(), element)
         })
@@ -370,5 +528,7 @@ fn convert_enum(path: String, e: &ast::Enum) -> model::Item {
         item_type: model::ItemType::Enum,
         elements,
         doc: e.doc.as_ref().cloned(),
+        annotations: Default::default(),
+        line: e.symbol_range.start.line_col.0,
     }
 }
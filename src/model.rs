@@ -1,24 +1,51 @@
+use indexmap::IndexMap;
 use serde_derive::Serialize;
-use std::collections::HashMap;
 
-#[derive(Serialize, Clone)]
+/// Version of the serialized output format. Bump this on any structural
+/// change to the emitted JSON/YAML so downstream consumers can detect
+/// breaking changes. Absence of an optional field means its default value
+/// is part of the contract.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Clone, schemars::JsonSchema)]
 pub struct Aidl {
+    #[serde(rename = "formatVersion")]
+    pub format_version: u32,
     pub root: String,
-    pub items: HashMap<String, Item>,
+    pub items: IndexMap<String, Item>,
+    /// Reverse references: fully-qualified item name -> element paths that
+    /// reference it. Absent when no references were computed.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub references: IndexMap<String, Vec<String>>,
+    /// Referenced type names that did not resolve to any declared item.
+    #[serde(
+        rename = "danglingReferences",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub dangling_references: Vec<String>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, schemars::JsonSchema)]
 pub struct Item {
     pub path: String,
     #[serde(rename = "itemType")]
     pub item_type: ItemType,
     pub name: String,
-    pub elements: HashMap<String, Element>,
+    pub elements: IndexMap<String, Element>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub doc: Option<String>,
+    /// Extra metadata injected by an overlay (tags, deprecation notes, ...).
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub annotations: IndexMap<String, String>,
+    /// 1-based source line of the item declaration. Not serialized; used for
+    /// query output.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub line: usize,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum ItemType {
     Interface,
@@ -26,7 +53,7 @@ pub enum ItemType {
     Enum,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, schemars::JsonSchema)]
 #[serde(tag = "elementType")]
 #[serde(rename_all = "camelCase")]
 #[allow(clippy::enum_variant_names)]
@@ -41,12 +68,18 @@ pub enum Element {
         value: Option<u32>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         doc: Option<String>,
+        #[serde(skip)]
+        #[schemars(skip)]
+        line: usize,
     },
     Const {
         name: String,
         #[serde(rename = "type")]
         const_type: String,
         value: String,
+        #[serde(skip)]
+        #[schemars(skip)]
+        line: usize,
     },
     Field {
         name: String,
@@ -54,6 +87,9 @@ pub enum Element {
         field_type: String,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         doc: Option<String>,
+        #[serde(skip)]
+        #[schemars(skip)]
+        line: usize,
     },
     EnumElement {
         name: String,
@@ -61,10 +97,25 @@ pub enum Element {
         value: Option<String>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         doc: Option<String>,
+        #[serde(skip)]
+        #[schemars(skip)]
+        line: usize,
     },
 }
 
-#[derive(Serialize, Clone)]
+impl Element {
+    /// 1-based source line of the element declaration.
+    pub fn line(&self) -> usize {
+        match self {
+            Element::Method { line, .. } => *line,
+            Element::Const { line, .. } => *line,
+            Element::Field { line, .. } => *line,
+            Element::EnumElement { line, .. } => *line,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, schemars::JsonSchema)]
 pub struct Arg {
     #[serde(default, skip_serializing_if = "Direction::is_unspecified")]
     pub direction: Direction,
@@ -75,12 +126,13 @@ pub struct Arg {
     pub doc: Option<String>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Default, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Direction {
     In,
     Out,
     InOut,
+    #[default]
     Unspecified,
 }
 
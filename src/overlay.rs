@@ -0,0 +1,109 @@
+use crate::model;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde_derive::Deserialize;
+use std::path::Path;
+
+/// A single overlay entry targeting an item (and optionally one of its
+/// elements) and the metadata to merge onto it. Loaded from a YAML list:
+///
+/// ```yaml
+/// - item: com.foo.IBar
+///   annotations:
+///     deprecated: "true"
+/// - item: com.foo.IBar
+///   element: doThing
+///   doc: "Does the thing."
+/// ```
+#[derive(Deserialize)]
+pub struct OverlayEntry {
+    /// Fully-qualified name of the target item.
+    pub item: String,
+    /// Optional element name within the item to target instead of the item.
+    #[serde(default)]
+    pub element: Option<String>,
+    /// Documentation string to inject or override.
+    #[serde(default)]
+    pub doc: Option<String>,
+    /// Annotations to merge onto the target item.
+    #[serde(default)]
+    pub annotations: IndexMap<String, String>,
+}
+
+/// Load an overlay document from a YAML file.
+pub fn load(path: &Path) -> Result<Vec<OverlayEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read overlay {}", path.display()))?;
+    let entries = serde_yaml::from_str(&content)
+        .with_context(|| format!("failed to parse overlay {}", path.display()))?;
+    Ok(entries)
+}
+
+/// Deep-merge the overlay entries onto the parsed model, matching each entry's
+/// `item` against the fully-qualified keys in `aidl.items`.
+pub fn apply(aidl: &mut model::Aidl, entries: &[OverlayEntry]) {
+    for entry in entries {
+        let item = match aidl.items.get_mut(&entry.item) {
+            Some(i) => i,
+            None => {
+                eprintln!("overlay: no item matching {}", entry.item);
+                continue;
+            }
+        };
+
+        match entry.element.as_ref() {
+            None => {
+                if let Some(doc) = entry.doc.as_ref() {
+                    item.doc = Some(doc.clone());
+                }
+                for (k, v) in &entry.annotations {
+                    item.annotations.insert(k.clone(), v.clone());
+                }
+            }
+            Some(element_name) => match item.elements.get_mut(element_name) {
+                Some(element) => {
+                    if let Some(doc) = entry.doc.as_ref() {
+                        if !set_element_doc(element, doc.clone()) {
+                            eprintln!(
+                                "overlay: doc is not supported on const elements, \
+                                 ignoring it for {}.{}",
+                                entry.item, element_name
+                            );
+                        }
+                    }
+                    if !entry.annotations.is_empty() {
+                        eprintln!(
+                            "overlay: annotations are not supported on elements, \
+                             ignoring them for {}.{}",
+                            entry.item, element_name
+                        );
+                    }
+                }
+                None => eprintln!(
+                    "overlay: no element {} in {}",
+                    element_name, entry.item
+                ),
+            },
+        }
+    }
+}
+
+// Set the doc string on an element, returning whether the variant supports
+// one (`Const` has no doc field, so it returns `false`).
+fn set_element_doc(element: &mut model::Element, value: String) -> bool {
+    match element {
+        model::Element::Method { doc, .. } => {
+            *doc = Some(value);
+            true
+        }
+        model::Element::Field { doc, .. } => {
+            *doc = Some(value);
+            true
+        }
+        model::Element::EnumElement { doc, .. } => {
+            *doc = Some(value);
+            true
+        }
+        model::Element::Const { .. } => false,
+    }
+}
@@ -0,0 +1,230 @@
+use crate::model;
+
+/// A parsed query: a conjunction of selector clauses applied to the in-memory
+/// [`model::Aidl`]. Clauses are separated by commas and must all match, e.g.
+///
+/// ```text
+/// itemType=interface,element.oneway=true
+/// arg.direction=out
+/// type~=Parcelable
+/// ```
+///
+/// Supported clauses:
+///
+///   * `itemType=interface|parcelable|enum` — item kind
+///   * `name~=<substr>` — fully-qualified item name contains `<substr>`
+///   * `element.oneway=true|false` — method one-way flag
+///   * `element.name~=<substr>` — element name contains `<substr>`
+///   * `arg.direction=in|out|inout|unspecified` — any arg with that direction
+///   * `type~=<substr>` — any return/arg/field/const type contains `<substr>`
+///
+/// An `=` clause matches exactly; `~=` matches a substring.
+pub struct Query {
+    clauses: Vec<Clause>,
+}
+
+enum Op {
+    Eq,
+    Contains,
+}
+
+struct Clause {
+    field: String,
+    op: Op,
+    value: String,
+}
+
+impl Clause {
+    fn parse(s: &str) -> Result<Clause, String> {
+        let (field, op, value) = if let Some(idx) = s.find("~=") {
+            (&s[..idx], Op::Contains, &s[idx + 2..])
+        } else if let Some(idx) = s.find('=') {
+            (&s[..idx], Op::Eq, &s[idx + 1..])
+        } else {
+            return Err(format!("invalid selector clause: {}", s));
+        };
+
+        Ok(Clause {
+            field: field.trim().to_string(),
+            op,
+            value: value.trim().to_string(),
+        })
+    }
+
+    fn test(&self, haystack: &str) -> bool {
+        match self.op {
+            Op::Eq => haystack == self.value,
+            Op::Contains => haystack.contains(&self.value),
+        }
+    }
+
+    // Whether the clause is scoped to individual elements (vs whole items).
+    fn is_element_scope(&self) -> bool {
+        self.field.starts_with("element.")
+            || self.field.starts_with("arg.")
+            || self.field == "type"
+    }
+}
+
+impl Query {
+    pub fn parse(selector: &str) -> Result<Query, String> {
+        let clauses = selector
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(Clause::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Query { clauses })
+    }
+
+    fn has_element_scope(&self) -> bool {
+        self.clauses.iter().any(Clause::is_element_scope)
+    }
+
+    // Does `item` (keyed by its fully-qualified `key`) pass all item-scope
+    // clauses?
+    fn item_matches(&self, key: &str, item: &model::Item) -> bool {
+        self.clauses.iter().filter(|c| !c.is_element_scope()).all(|c| {
+            match c.field.as_str() {
+                "itemType" => c.test(item_type_str(&item.item_type)),
+                "name" => c.test(key),
+                _ => false,
+            }
+        })
+    }
+
+    // Does `element` pass all element-scope clauses?
+    fn element_matches(&self, element: &model::Element) -> bool {
+        self.clauses.iter().filter(|c| c.is_element_scope()).all(|c| {
+            match c.field.as_str() {
+                "element.oneway" => matches!(
+                    element,
+                    model::Element::Method { oneway, .. } if c.test(&oneway.to_string())
+                ),
+                "element.name" => c.test(element_name(element)),
+                "arg.direction" => match element {
+                    model::Element::Method { args, .. } => args
+                        .iter()
+                        .any(|a| c.test(direction_str(&a.direction))),
+                    _ => false,
+                },
+                "type" => element_types(element).iter().any(|t| c.test(t)),
+                _ => false,
+            }
+        })
+    }
+
+    /// Flat list of matches as `<path>:<line>: <key>` (for item-only queries)
+    /// or `<path>:<line>: <key>#<element>` (when element-scope clauses are
+    /// present), where `<line>` is the 1-based source line.
+    pub fn flat(&self, aidl: &model::Aidl) -> Vec<String> {
+        let mut out = Vec::new();
+        let element_scope = self.has_element_scope();
+
+        for (key, item) in &aidl.items {
+            if !self.item_matches(key, item) {
+                continue;
+            }
+
+            if !element_scope {
+                out.push(format!("{}:{}: {}", item.path, item.line, key));
+                continue;
+            }
+
+            for (name, element) in &item.elements {
+                if self.element_matches(element) {
+                    out.push(format!(
+                        "{}:{}: {}#{}",
+                        item.path,
+                        element.line(),
+                        key,
+                        name
+                    ));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// A reduced [`model::Aidl`] keeping only the matching items (and, for
+    /// element-scope queries, only the matching elements), for serialization
+    /// through the existing output path.
+    pub fn filter(&self, aidl: &model::Aidl) -> model::Aidl {
+        let element_scope = self.has_element_scope();
+        let mut items = indexmap::IndexMap::new();
+
+        for (key, item) in &aidl.items {
+            if !self.item_matches(key, item) {
+                continue;
+            }
+
+            if !element_scope {
+                items.insert(key.clone(), item.clone());
+                continue;
+            }
+
+            let elements: indexmap::IndexMap<_, _> = item
+                .elements
+                .iter()
+                .filter(|(_, el)| self.element_matches(el))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+
+            if !elements.is_empty() {
+                let mut item = item.clone();
+                item.elements = elements;
+                items.insert(key.clone(), item);
+            }
+        }
+
+        model::Aidl {
+            format_version: aidl.format_version,
+            root: aidl.root.clone(),
+            items,
+            references: Default::default(),
+            dangling_references: Default::default(),
+        }
+    }
+}
+
+fn item_type_str(t: &model::ItemType) -> &'static str {
+    match t {
+        model::ItemType::Interface => "interface",
+        model::ItemType::Parcelable => "parcelable",
+        model::ItemType::Enum => "enum",
+    }
+}
+
+fn direction_str(d: &model::Direction) -> &'static str {
+    match d {
+        model::Direction::In => "in",
+        model::Direction::Out => "out",
+        model::Direction::InOut => "inout",
+        model::Direction::Unspecified => "unspecified",
+    }
+}
+
+fn element_name(el: &model::Element) -> &str {
+    match el {
+        model::Element::Method { name, .. } => name,
+        model::Element::Const { name, .. } => name,
+        model::Element::Field { name, .. } => name,
+        model::Element::EnumElement { name, .. } => name,
+    }
+}
+
+// All type strings referenced by an element (return, args, field, const).
+fn element_types(el: &model::Element) -> Vec<String> {
+    match el {
+        model::Element::Method {
+            return_type, args, ..
+        } => {
+            let mut v = vec![return_type.clone()];
+            v.extend(args.iter().map(|a| a.arg_type.clone()));
+            v
+        }
+        model::Element::Const { const_type, .. } => vec![const_type.clone()],
+        model::Element::Field { field_type, .. } => vec![field_type.clone()],
+        model::Element::EnumElement { .. } => Vec::new(),
+    }
+}
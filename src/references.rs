@@ -0,0 +1,116 @@
+use crate::model;
+use indexmap::IndexMap;
+
+// AIDL primitives and built-in types that never resolve to a declared item.
+const BUILTINS: &[&str] = &[
+    "void",
+    "boolean",
+    "byte",
+    "char",
+    "int",
+    "long",
+    "float",
+    "double",
+    "String",
+    "CharSequence",
+    "IBinder",
+    "FileDescriptor",
+    "ParcelFileDescriptor",
+    "List",
+    "Map",
+];
+
+/// The cross-reference graph computed over the parsed model: for each declared
+/// item (keyed by its fully-qualified name) the set of element paths that
+/// reference it, plus the list of references that did not resolve to any
+/// declared item.
+pub struct References {
+    /// `FQN -> [referencing element paths]`, e.g.
+    /// `"com.foo.IBar" -> ["com.foo.IBaz.doThing.arg0"]`.
+    pub references: IndexMap<String, Vec<String>>,
+    /// Referenced type names that matched no declared item.
+    pub dangling: Vec<String>,
+}
+
+// Recurse into generic container types (`List<T>`, `Map<K,V>`) and collect the
+// leaf type names.
+fn leaf_types(aidl_type: &str, out: &mut Vec<String>) {
+    if let Some(open) = aidl_type.find('<') {
+        if aidl_type.ends_with('>') {
+            let inner = &aidl_type[open + 1..aidl_type.len() - 1];
+            let mut depth = 0;
+            let mut start = 0;
+            for (i, c) in inner.char_indices() {
+                match c {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    ',' if depth == 0 => {
+                        leaf_types(inner[start..i].trim(), out);
+                        start = i + 1;
+                    }
+                    _ => {}
+                }
+            }
+            leaf_types(inner[start..].trim(), out);
+            return;
+        }
+    }
+    out.push(aidl_type.to_string());
+}
+
+// Every (type, source-path) reference emitted by an element.
+fn element_refs(key: &str, name: &str, element: &model::Element) -> Vec<(String, String)> {
+    let mut refs = Vec::new();
+    match element {
+        model::Element::Method {
+            return_type, args, ..
+        } => {
+            refs.push((return_type.clone(), format!("{}.{}.return", key, name)));
+            for (i, a) in args.iter().enumerate() {
+                refs.push((a.arg_type.clone(), format!("{}.{}.arg{}", key, name, i)));
+            }
+        }
+        model::Element::Const { const_type, .. } => {
+            refs.push((const_type.clone(), format!("{}.{}", key, name)));
+        }
+        model::Element::Field { field_type, .. } => {
+            refs.push((field_type.clone(), format!("{}.{}", key, name)));
+        }
+        model::Element::EnumElement { .. } => {}
+    }
+    refs
+}
+
+/// Build the cross-reference graph from a fully-populated model.
+pub fn build(aidl: &model::Aidl) -> References {
+    let mut references: IndexMap<String, Vec<String>> = IndexMap::new();
+    let mut dangling: Vec<String> = Vec::new();
+
+    for (key, item) in &aidl.items {
+        for (name, element) in &item.elements {
+            for (ty, source) in element_refs(key, name, element) {
+                let mut leaves = Vec::new();
+                leaf_types(&ty, &mut leaves);
+
+                for leaf in leaves {
+                    if BUILTINS.contains(&leaf.as_str()) {
+                        continue;
+                    }
+                    if aidl.items.contains_key(&leaf) {
+                        let sources = references.entry(leaf).or_default();
+                        if !sources.contains(&source) {
+                            sources.push(source.clone());
+                        }
+                    } else if !dangling.contains(&leaf) {
+                        dangling.push(leaf);
+                    }
+                }
+            }
+        }
+    }
+
+    References {
+        references,
+        dangling,
+    }
+}
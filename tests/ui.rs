@@ -0,0 +1,137 @@
+//! Snapshot/golden test harness.
+//!
+//! Recursively discovers every `.aidl` file under `tests/ui`, runs it through
+//! the `aidl-cli` binary (which parses via `parser.validate()` and walks the
+//! `convert` path) and compares:
+//!
+//!   * the pretty JSON model against a sibling `<name>.aidl.json`, and
+//!   * the rendered diagnostics against a sibling `<name>.aidl.diag`.
+//!
+//! All mismatches are collected and reported at the end instead of failing on
+//! the first one. Set `BLESS=1` to (re)generate the expectation files in place:
+//!
+//! ```sh
+//! BLESS=1 cargo test --test ui
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Run the binary over a single fixture file's parent directory, returning
+// (stdout = pretty JSON model, stderr = diagnostics).
+fn run_cli(fixture: &Path) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_aidl-cli"))
+        .arg("--to-json")
+        .arg("--pretty")
+        .arg(fixture.parent().unwrap())
+        .output()
+        .expect("failed to run aidl-cli");
+
+    (
+        normalize_root(&String::from_utf8_lossy(&output.stdout)),
+        strip_progress(&String::from_utf8_lossy(&output.stderr)),
+    )
+}
+
+// The model embeds `root: current_dir()`, an absolute, checkout-dependent
+// path. Replace its value with a placeholder so goldens are portable.
+fn normalize_root(json: &str) -> String {
+    json.lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("\"root\":") {
+                let indent = &line[..line.len() - trimmed.len()];
+                format!("{}\"root\": \"<root>\",", indent)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Drop the per-file progress dots emitted by `eprint!(".")` before the
+// diagnostics so they do not pollute the `.diag` goldens.
+fn strip_progress(diagnostics: &str) -> String {
+    diagnostics
+        .trim_start_matches('.')
+        .trim_start_matches('\n')
+        .to_string()
+}
+
+// Compare `actual` against the expectation stored at `expected_path`, or bless
+// it when `BLESS` is set. Pushes a human-readable message to `failures` on
+// mismatch.
+fn check(expected_path: &Path, actual: &str, failures: &mut Vec<String>) {
+    let blessing = std::env::var_os("BLESS").is_some();
+
+    if blessing {
+        std::fs::write(expected_path, actual).unwrap();
+        return;
+    }
+
+    let expected = match std::fs::read_to_string(expected_path) {
+        Ok(e) => e,
+        Err(_) => {
+            failures.push(format!(
+                "missing expectation {} (run with BLESS=1 to create it)",
+                expected_path.display()
+            ));
+            return;
+        }
+    };
+
+    if expected != actual {
+        failures.push(format!("mismatch in {}", expected_path.display()));
+    }
+}
+
+// Collect every `.aidl` fixture under `tests/ui`.
+fn fixtures() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/ui");
+    let mut out = Vec::new();
+
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, out);
+            } else if path.extension().and_then(|e| e.to_str()) == Some("aidl") {
+                out.push(path);
+            }
+        }
+    }
+
+    walk(&root, &mut out);
+    out.sort();
+    out
+}
+
+#[test]
+fn ui() {
+    let mut failures = Vec::new();
+
+    for fixture in fixtures() {
+        let (json, diagnostics) = run_cli(&fixture);
+
+        let mut json_path = fixture.clone().into_os_string();
+        json_path.push(".json");
+        check(Path::new(&json_path), &json, &mut failures);
+
+        let mut diag_path = fixture.clone().into_os_string();
+        diag_path.push(".diag");
+        check(Path::new(&diag_path), &diagnostics, &mut failures);
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "{} golden test failure(s):\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+    }
+}